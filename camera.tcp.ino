@@ -1,4 +1,6 @@
 #include <WiFi.h>
+#include <ESPmDNS.h>
+#include <lwip/sockets.h>
 #include "esp_camera.h"
 #include "camera_config.h"
 
@@ -8,10 +10,40 @@
 #define WIFI_PASSWORD ""
 // TCP port
 #define PORT 1234
+// Number of pending connections the TCP listener queues before refusing new ones
+#define LISTEN_BACKLOG 4
+// DSCP value (0-63) to mark streamed packets with for network QoS (-1 = off)
+#define DSCP -1
+// Advertise the stream on the local network using mDNS (0 = off)
+#define DISCOVERY 0
+// mDNS host name (the server is reachable as <DISCOVERY_HOSTNAME>.local)
+#define DISCOVERY_HOSTNAME "esp32cam"
 // Frames Per Second
 #define FPS 30.0
+// Send a text banner to each client before the JPEG stream (0 = off).
+// Leave this off when capturing the raw stream (e.g. `nc ... > frame.jpg`),
+// since the banner would end up in front of the first image.
+#define CLIENT_BANNER 0
 
-WiFiServer server(PORT);
+// Frame delimiter modes (see README)
+#define DELIMITER_NONE 0
+#define DELIMITER_SOI 1
+#define DELIMITER_LENGTH 2
+// How consecutive frames are separated in the stream
+#define CLIENT_DELIMITER DELIMITER_NONE
+// Close client connections after this many seconds (0 = never)
+#define CLIENT_MAX_LIFETIME 0
+// Maximum bytes per second sent to a client (0 = unlimited). Frames that
+// would exceed the budget are skipped rather than delayed.
+#define CLIENT_MAX_BPS 0
+
+// Initial frame modes (see README)
+#define INITIAL_FRAME_CACHED 0
+#define INITIAL_FRAME_WAIT 1
+// What the first frame sent to a new client is
+#define CLIENT_INITIAL_FRAME INITIAL_FRAME_CACHED
+
+WiFiServer server(PORT, LISTEN_BACKLOG);
 
 void setup() {
   Serial.begin(115200);
@@ -34,8 +66,30 @@ void setup() {
 
   // Start TCP server
   server.begin();
-  Serial.printf("Server started on port %d\n", PORT);
+  Serial.printf("Server started on port %d (backlog %d)\n", PORT, LISTEN_BACKLOG);
   Serial.print("Address: "); Serial.println(WiFi.localIP());
+
+  if (DISCOVERY) {
+    if (MDNS.begin(DISCOVERY_HOSTNAME)) {
+      MDNS.addService("esp32cam", "tcp", PORT);
+      MDNS.addServiceTxt("esp32cam", "tcp", "format", "jpeg");
+      Serial.printf("Advertising as %s.local\n", DISCOVERY_HOSTNAME);
+    } else {
+      Serial.println("Failed to start mDNS");
+    }
+  }
+}
+
+// Sends a short description of the stream, terminated by a blank line.
+void sendBanner(WiFiClient &client) {
+  sensor_t *s = esp_camera_sensor_get();
+  client.print("ESP32-CAM TCP server\r\n");
+  client.print("Format: JPEG\r\n");
+  if (s) {
+    framesize_t size = (framesize_t)s->status.framesize;
+    client.printf("Size: %dx%d\r\n", resolution[size].width, resolution[size].height);
+  }
+  client.print("\r\n");
 }
 
 void loop() {
@@ -43,8 +97,56 @@ void loop() {
   if (client) {
     Serial.print("New client connected: ");
     Serial.println(client.remoteIP());
+
+    if (DSCP >= 0) {
+      // The DSCP occupies the upper six bits of the IP TOS byte
+      int tos = (DSCP & 0x3f) << 2;
+      if (setsockopt(client.fd(), IPPROTO_IP, IP_TOS, &tos, sizeof(tos)) != 0) {
+        Serial.println("Failed to set DSCP");
+      }
+    }
+
+    if (CLIENT_BANNER) {
+      sendBanner(client);
+    }
+
+    if (CLIENT_DELIMITER == DELIMITER_SOI) {
+      // Push each frame out as soon as it is written instead of letting
+      // its tail be coalesced with the start of the next one.
+      client.setNoDelay(true);
+    }
+
+    if (CLIENT_INITIAL_FRAME == INITIAL_FRAME_WAIT) {
+      // The buffer held by the driver may have been captured long before
+      // the client connected; throw it away so the first frame is live.
+      camera_fb_t *stale = esp_camera_fb_get();
+      if (stale) {
+        esp_camera_fb_return(stale);
+      }
+    }
+
+    unsigned long connectedAt = millis();
+
+    // Token bucket for CLIENT_MAX_BPS. The budget may go negative after a
+    // large frame; frames are skipped until it has been paid back.
+    float byteBudget = 0;
+    unsigned long budgetUpdatedAt = connectedAt;
+    unsigned long droppedForBandwidth = 0;
+
     while (client.connected()) {
 
+      if (CLIENT_MAX_LIFETIME > 0 && millis() - connectedAt >= CLIENT_MAX_LIFETIME * 1000UL) {
+        Serial.println("Closing client: max_lifetime_reached");
+        break;
+      }
+
+      // Discard anything the client sends so its data doesn't pile up in
+      // the receive buffer (the stream is one-way)
+      uint8_t discard[64];
+      while (client.available() > 0) {
+        client.read(discard, sizeof(discard));
+      }
+
       // Capture image
       camera_fb_t *fb = esp_camera_fb_get();
       if (!fb) {
@@ -52,14 +154,48 @@ void loop() {
         break;
       }
 
-      // Send image data over TCP
-      if (client.write(fb->buf, fb->len) != fb->len) {
-        Serial.println("Error sending image");
+      bool skipFrame = false;
+      if (CLIENT_MAX_BPS > 0) {
+        unsigned long now = millis();
+        byteBudget += (now - budgetUpdatedAt) * (CLIENT_MAX_BPS / 1000.0);
+        byteBudget = min(byteBudget, (float)CLIENT_MAX_BPS);
+        budgetUpdatedAt = now;
+
+        if (byteBudget < 0) {
+          skipFrame = true;
+          droppedForBandwidth++;
+        } else {
+          byteBudget -= fb->len;
+        }
+      }
+
+      bool sendFailed = false;
+      if (!skipFrame) {
+        if (CLIENT_DELIMITER == DELIMITER_LENGTH) {
+          // Prefix each frame with its size as a 4 byte big-endian integer
+          uint8_t header[4] = {
+            (uint8_t)(fb->len >> 24), (uint8_t)(fb->len >> 16),
+            (uint8_t)(fb->len >> 8), (uint8_t)fb->len
+          };
+          sendFailed = client.write(header, sizeof(header)) != sizeof(header);
+        }
+
+        // Send image data over TCP
+        if (!sendFailed && client.write(fb->buf, fb->len) != fb->len) {
+          sendFailed = true;
+        }
       }
 
       // Free image buffer
       esp_camera_fb_return(fb);
-      
+
+      // A partially written frame leaves the client mid-image (or mid-header),
+      // so don't continue with the next frame; let the client reconnect.
+      if (sendFailed) {
+        Serial.println("Error sending image, closing connection");
+        break;
+      }
+
       // Delay between frames (adjust as needed)
       delay(1000.0 / FPS);
     }
@@ -67,5 +203,8 @@ void loop() {
     // Close client connection
     client.stop();
     Serial.println("Client disconnected");
+    if (CLIENT_MAX_BPS > 0) {
+      Serial.printf("Frames dropped for bandwidth: %lu\n", droppedForBandwidth);
+    }
   }
 }
\ No newline at end of file